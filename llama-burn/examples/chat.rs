@@ -3,7 +3,7 @@ use std::time::Instant;
 use burn::tensor::{backend::Backend, Device};
 use clap::Parser;
 use llama_burn::{
-    llama::{Llama, LlamaConfig},
+    llama::{GenerateOptions, Llama, LlamaConfig},
     sampling::{Sampler, TopP},
     tokenizer::Tokenizer,
 };
@@ -21,10 +21,6 @@ pub struct Config {
     #[arg(long, default_value_t = 0.6)]
     temperature: f64,
 
-    /// Maximum sequence length for input text.
-    #[arg(long, default_value_t = 128)]
-    max_seq_len: usize,
-
     /// The number of new tokens to generate (i.e., the number of generation steps to take).
     #[arg(long, short = 'n', default_value_t = 50)]
     sample_len: usize,
@@ -36,6 +32,16 @@ pub struct Config {
     /// The input prompt.
     #[arg(short, long, default_value_t = String::from(DEFAULT_PROMPT))]
     prompt: String,
+
+    /// Start an interactive, multi-turn chat REPL instead of generating once and exiting. The
+    /// KV cache is reused across turns, so only each new turn's text is prefilled.
+    #[arg(short, long, default_value_t = false)]
+    interactive: bool,
+
+    /// Hugging Face Hub token, for the gated Llama-3 repo. Falls back to the `HF_TOKEN`
+    /// environment variable if not set.
+    #[arg(long)]
+    hf_token: Option<String>,
 }
 
 pub fn generate<B: Backend, T: Tokenizer>(
@@ -63,8 +69,63 @@ pub fn generate<B: Backend, T: Tokenizer>(
     );
 }
 
+/// Run an interactive multi-turn REPL against `llama`, reading user input from stdin.
+///
+/// `system_prompt` formats the system turn shown once at the start of the conversation,
+/// `user_turn` formats each subsequent user message; both follow the model's own chat template.
+/// Because `Llama` keeps its KV cache and conversation history between calls, each turn only
+/// prefills the text added by that turn rather than re-encoding everything said so far.
+fn interactive<B: Backend, T: Tokenizer>(
+    llama: &mut Llama<B, T>,
+    sample_len: usize,
+    temperature: f64,
+    sampler: &mut Sampler,
+    system_prompt: impl FnOnce() -> String,
+    user_turn: impl Fn(&str) -> String,
+) {
+    use std::io::{stdin, stdout, Write};
+
+    let mut first_turn = true;
+    println!("Entering interactive mode (Ctrl-D to exit).\n");
+
+    loop {
+        print!("> ");
+        stdout().flush().ok();
+
+        let mut line = String::new();
+        if stdin().read_line(&mut line).unwrap_or(0) == 0 {
+            println!();
+            break;
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let turn = if first_turn {
+            first_turn = false;
+            format!("{}{}", system_prompt(), user_turn(line))
+        } else {
+            user_turn(line)
+        };
+
+        llama.generate_stream(
+            &turn,
+            sample_len,
+            temperature,
+            sampler,
+            &GenerateOptions::default(),
+            |fragment| {
+                print!("{fragment}");
+                stdout().flush().ok();
+            },
+        );
+        println!("\n");
+    }
+}
+
 pub fn chat<B: Backend>(args: Config, device: Device<B>) {
-    let mut prompt = args.prompt;
+    let mut prompt = args.prompt.clone();
 
     // Sampling strategy
     let mut sampler = if args.temperature > 0.0 {
@@ -77,40 +138,74 @@ pub fn chat<B: Backend>(args: Config, device: Device<B>) {
     {
         // TinyLlama-1.1B Chat v1.0
         let mut llama = LlamaConfig::tiny_llama_pretrained::<B>(&device).unwrap();
-        println!("Processing prompt: {}", prompt);
 
-        // Prompt formatting for chat model
-        prompt = format!(
-            "<|system|>\nYou are a friendly chatbot who always responds in the style of a pirate</s>\n<|user|>\n{prompt}</s>\n<|assistant|>\n"
-        );
+        let system_prompt = || {
+            "<|system|>\nYou are a friendly chatbot who always responds in the style of a pirate</s>\n"
+                .to_string()
+        };
+        let user_turn = |user: &str| format!("<|user|>\n{user}</s>\n<|assistant|>\n");
 
-        generate(
-            &mut llama,
-            &prompt,
-            args.sample_len,
-            args.temperature,
-            &mut sampler,
-        );
+        if args.interactive {
+            interactive(
+                &mut llama,
+                args.sample_len,
+                args.temperature,
+                &mut sampler,
+                system_prompt,
+                user_turn,
+            );
+        } else {
+            println!("Processing prompt: {}", prompt);
+            prompt = format!("{}{}", system_prompt(), user_turn(&prompt));
+
+            generate(
+                &mut llama,
+                &prompt,
+                args.sample_len,
+                args.temperature,
+                &mut sampler,
+            );
+        }
     }
 
     #[cfg(feature = "llama3")]
     {
         // Llama-3-8B-Instruct
-        let mut llama = LlamaConfig::llama3_8b_pretrained::<B>(true, &device).unwrap();
-        println!("Processing prompt: {}", prompt);
+        let mut llama =
+            LlamaConfig::llama3_8b_pretrained::<B>(true, args.hf_token.as_deref(), &device)
+                .unwrap();
 
-        // Prompt formatting for chat model
-        prompt = format!(
-            "<|start_header_id|>system<|end_header_id|>\n\nA chat between a curious user and an artificial intelligence assistant. The assistant gives helpful, detailed, and polite answers to the user's questions.<|eot_id|><|start_header_id|>user<|end_header_id|>\n\n{prompt}<|eot_id|><|start_header_id|>assistant<|end_header_id|>\n\n"
-        );
+        let system_prompt = || {
+            "<|start_header_id|>system<|end_header_id|>\n\nA chat between a curious user and an artificial intelligence assistant. The assistant gives helpful, detailed, and polite answers to the user's questions.<|eot_id|>"
+                .to_string()
+        };
+        let user_turn = |user: &str| {
+            format!(
+                "<|start_header_id|>user<|end_header_id|>\n\n{user}<|eot_id|><|start_header_id|>assistant<|end_header_id|>\n\n"
+            )
+        };
 
-        generate(
-            &mut llama,
-            &prompt,
-            args.sample_len,
-            args.temperature,
-            &mut sampler,
-        );
+        if args.interactive {
+            interactive(
+                &mut llama,
+                args.sample_len,
+                args.temperature,
+                &mut sampler,
+                system_prompt,
+                user_turn,
+            );
+        } else {
+            println!("Processing prompt: {}", prompt);
+            prompt = format!("{}{}", system_prompt(), user_turn(&prompt));
+
+            generate(
+                &mut llama,
+                &prompt,
+                args.sample_len,
+                args.temperature,
+                &mut sampler,
+            );
+        }
     }
 }
 
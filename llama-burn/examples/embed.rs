@@ -0,0 +1,113 @@
+use burn::tensor::{backend::Backend, Device};
+use clap::Parser;
+use llama_burn::llama::{Llama, LlamaConfig, Pooling};
+
+const DEFAULT_TEXT: &str = "How many helicopters can a human eat in one sitting?";
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+pub struct Config {
+    /// Pooling strategy used to turn the per-token hidden states into a single embedding vector.
+    #[arg(long, value_enum, default_value_t = PoolingArg::Mean)]
+    pooling: PoolingArg,
+
+    /// L2-normalize the resulting embedding.
+    #[arg(long, default_value_t = false)]
+    normalize: bool,
+
+    /// The text to embed.
+    #[arg(short, long, default_value_t = String::from(DEFAULT_TEXT))]
+    text: String,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum PoolingArg {
+    Mean,
+    LastToken,
+}
+
+impl From<PoolingArg> for Pooling {
+    fn from(arg: PoolingArg) -> Self {
+        match arg {
+            PoolingArg::Mean => Pooling::Mean,
+            PoolingArg::LastToken => Pooling::LastToken,
+        }
+    }
+}
+
+pub fn embed<B: Backend>(llama: &mut Llama<B, impl llama_burn::tokenizer::Tokenizer>, args: &Config) {
+    let embedding = llama.embed(&args.text, args.pooling.into(), args.normalize);
+    let values: Vec<f32> = embedding.into_data().convert::<f32>().to_vec().unwrap();
+
+    println!("{} dims: {:?}...", values.len(), &values[..values.len().min(8)]);
+}
+
+pub fn run<B: Backend>(args: Config, device: Device<B>) {
+    println!("Embedding: {}", args.text);
+
+    #[cfg(feature = "tiny")]
+    {
+        let mut llama = LlamaConfig::tiny_llama_pretrained::<B>(&device).unwrap();
+        embed(&mut llama, &args);
+    }
+
+    #[cfg(feature = "llama3")]
+    {
+        let mut llama = LlamaConfig::llama3_8b_pretrained::<B>(false, None, &device).unwrap();
+        embed(&mut llama, &args);
+    }
+}
+
+#[cfg(feature = "tch-gpu")]
+mod tch_gpu {
+    use super::*;
+    use burn::{
+        backend::{libtorch::LibTorchDevice, LibTorch},
+        tensor::f16,
+    };
+
+    pub fn run(args: Config) {
+        #[cfg(not(target_os = "macos"))]
+        let device = LibTorchDevice::Cuda(0);
+        #[cfg(target_os = "macos")]
+        let device = LibTorchDevice::Mps;
+
+        super::run::<LibTorch<f16>>(args, device);
+    }
+}
+
+#[cfg(feature = "tch-cpu")]
+mod tch_cpu {
+    use super::*;
+    use burn::backend::{libtorch::LibTorchDevice, LibTorch};
+
+    pub fn run(args: Config) {
+        let device = LibTorchDevice::Cpu;
+
+        super::run::<LibTorch>(args, device);
+    }
+}
+
+#[cfg(feature = "wgpu")]
+mod wgpu {
+    use super::*;
+    use burn::backend::wgpu::{Wgpu, WgpuDevice};
+
+    pub fn run(args: Config) {
+        let device = WgpuDevice::default();
+
+        super::run::<Wgpu>(args, device);
+    }
+}
+
+pub fn main() {
+    // Parse arguments
+    let args = Config::parse();
+
+    #[cfg(feature = "tch-gpu")]
+    tch_gpu::run(args);
+    #[cfg(feature = "tch-cpu")]
+    tch_cpu::run(args);
+    #[cfg(feature = "wgpu")]
+    wgpu::run(args);
+}
@@ -0,0 +1,223 @@
+use std::io::Write;
+use std::time::Instant;
+
+use burn::tensor::{backend::Backend, Device};
+use clap::Parser;
+use llama_burn::{
+    llama::{GenerateOptions, Llama, LlamaConfig},
+    sampling::{FrequencyPresencePenalty, MinP, RepetitionPenalty, Sampler, TopKThenTopP, TopP},
+    tokenizer::Tokenizer,
+};
+
+const DEFAULT_PROMPT: &str = "How many helicopters can a human eat in one sitting?";
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+pub struct Config {
+    /// Top-p probability threshold.
+    #[arg(long, default_value_t = 0.9)]
+    top_p: f64,
+
+    /// Top-k: restrict sampling to the k highest-probability tokens. Always followed by top-p
+    /// filtering with `--top-p` (its default value when not given).
+    #[arg(long)]
+    top_k: Option<usize>,
+
+    /// Min-p: keep only tokens with probability at least `min_p * max_prob`. Takes priority
+    /// over `--top-k`/`--top-p` when set.
+    #[arg(long)]
+    min_p: Option<f64>,
+
+    /// Repetition penalty applied to the logits of previously generated tokens (1.0 disables
+    /// it; values above 1.0 discourage repeats).
+    #[arg(long, default_value_t = 1.0)]
+    repeat_penalty: f64,
+
+    /// Number of trailing generated tokens considered by `--repeat-penalty`.
+    #[arg(long, default_value_t = 64)]
+    repeat_last_n: usize,
+
+    /// Subtracts `count * frequency_penalty` from the logit of every token among the last
+    /// `--repeat-last-n` generated tokens, where `count` is how many times it occurred there
+    /// (0.0 disables it).
+    #[arg(long, default_value_t = 0.0)]
+    frequency_penalty: f64,
+
+    /// Subtracts a flat `presence_penalty` from the logit of every token that has occurred at
+    /// least once among the last `--repeat-last-n` generated tokens (0.0 disables it).
+    #[arg(long, default_value_t = 0.0)]
+    presence_penalty: f64,
+
+    /// Stop generation as soon as any of these strings appear in the output (repeatable). The
+    /// matched string is trimmed from the printed text.
+    #[arg(long)]
+    stop: Vec<String>,
+
+    /// Keep generating for the full `--sample-len` instead of stopping at the tokenizer's
+    /// end-of-text / end-of-turn token.
+    #[arg(long, default_value_t = false)]
+    ignore_eos: bool,
+
+    /// Temperature value for controlling randomness in sampling.
+    #[arg(long, default_value_t = 0.6)]
+    temperature: f64,
+
+    /// The maximum number of new tokens to generate (i.e., the maximum number of generation
+    /// steps to take); generation ends earlier on a `--stop` string or an end-of-text token.
+    #[arg(long, short = 'n', default_value_t = 50)]
+    sample_len: usize,
+
+    /// The seed to use when generating random samples.
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+
+    /// The input prompt.
+    #[arg(short, long, default_value_t = String::from(DEFAULT_PROMPT))]
+    prompt: String,
+
+    /// Hugging Face Hub token, for the gated Llama-3 repo. Falls back to the `HF_TOKEN`
+    /// environment variable if not set.
+    #[arg(long)]
+    hf_token: Option<String>,
+}
+
+pub fn generate<B: Backend, T: Tokenizer>(
+    llama: &mut Llama<B, T>,
+    prompt: &str,
+    sample_len: usize,
+    temperature: f64,
+    sampler: &mut Sampler,
+    options: &GenerateOptions,
+) {
+    let now = Instant::now();
+    let tokens = llama.generate_stream(prompt, sample_len, temperature, sampler, options, |fragment| {
+        print!("{fragment}");
+        std::io::stdout().flush().ok();
+    });
+    println!();
+    let elapsed = now.elapsed().as_secs();
+
+    println!(
+        "\n{} tokens generated ({:.4} tokens/s)\n",
+        tokens,
+        tokens as f64 / now.elapsed().as_secs_f64()
+    );
+
+    println!(
+        "Generation completed in {}m{}s",
+        (elapsed / 60),
+        elapsed % 60
+    );
+}
+
+pub fn run<B: Backend>(args: Config, device: Device<B>) {
+    // Sampling strategy
+    let mut sampler = if args.temperature <= 0.0 {
+        Sampler::Argmax
+    } else if let Some(min_p) = args.min_p {
+        Sampler::MinP(MinP::new(min_p, args.seed))
+    } else if let Some(top_k) = args.top_k {
+        Sampler::TopKThenTopP(TopKThenTopP::new(top_k, args.top_p, args.seed))
+    } else {
+        Sampler::TopP(TopP::new(args.top_p, args.seed))
+    };
+
+    let repetition_penalty = (args.repeat_penalty != 1.0)
+        .then(|| RepetitionPenalty::new(args.repeat_penalty, args.repeat_last_n));
+    let frequency_presence_penalty = (args.frequency_penalty != 0.0 || args.presence_penalty != 0.0)
+        .then(|| {
+            FrequencyPresencePenalty::new(args.frequency_penalty, args.presence_penalty, args.repeat_last_n)
+        });
+    let options = GenerateOptions {
+        repetition_penalty: repetition_penalty.as_ref(),
+        frequency_presence_penalty: frequency_presence_penalty.as_ref(),
+        stop_strings: &args.stop,
+        ignore_eos: args.ignore_eos,
+    };
+
+    #[cfg(feature = "tiny")]
+    {
+        let mut llama = LlamaConfig::tiny_llama_pretrained::<B>(&device).unwrap();
+        println!("Processing prompt: {}", args.prompt);
+
+        generate(
+            &mut llama,
+            &args.prompt,
+            args.sample_len,
+            args.temperature,
+            &mut sampler,
+            &options,
+        );
+    }
+
+    #[cfg(feature = "llama3")]
+    {
+        let mut llama =
+            LlamaConfig::llama3_8b_pretrained::<B>(false, args.hf_token.as_deref(), &device)
+                .unwrap();
+        println!("Processing prompt: {}", args.prompt);
+
+        generate(
+            &mut llama,
+            &args.prompt,
+            args.sample_len,
+            args.temperature,
+            &mut sampler,
+            &options,
+        );
+    }
+}
+
+#[cfg(feature = "tch-gpu")]
+mod tch_gpu {
+    use super::*;
+    use burn::{
+        backend::{libtorch::LibTorchDevice, LibTorch},
+        tensor::f16,
+    };
+
+    pub fn run(args: Config) {
+        #[cfg(not(target_os = "macos"))]
+        let device = LibTorchDevice::Cuda(0);
+        #[cfg(target_os = "macos")]
+        let device = LibTorchDevice::Mps;
+
+        super::run::<LibTorch<f16>>(args, device);
+    }
+}
+
+#[cfg(feature = "tch-cpu")]
+mod tch_cpu {
+    use super::*;
+    use burn::backend::{libtorch::LibTorchDevice, LibTorch};
+
+    pub fn run(args: Config) {
+        let device = LibTorchDevice::Cpu;
+
+        super::run::<LibTorch>(args, device);
+    }
+}
+
+#[cfg(feature = "wgpu")]
+mod wgpu {
+    use super::*;
+    use burn::backend::wgpu::{Wgpu, WgpuDevice};
+
+    pub fn run(args: Config) {
+        let device = WgpuDevice::default();
+
+        super::run::<Wgpu>(args, device);
+    }
+}
+
+pub fn main() {
+    // Parse arguments
+    let args = Config::parse();
+
+    #[cfg(feature = "tch-gpu")]
+    tch_gpu::run(args);
+    #[cfg(feature = "tch-cpu")]
+    tch_cpu::run(args);
+    #[cfg(feature = "wgpu")]
+    wgpu::run(args);
+}
@@ -0,0 +1,65 @@
+use burn::tensor::{backend::Backend, Tensor};
+
+/// Autoregressive key-value cache for a single attention layer.
+///
+/// Each call to [`AutoregressiveCache::forward`] appends the newly computed keys/values to
+/// whatever was cached from previous calls and returns the full sequence seen so far, so the
+/// transformer only ever has to project the newly added tokens.
+pub struct AutoregressiveCache<B: Backend> {
+    cache: Option<(Tensor<B, 4>, Tensor<B, 4>)>,
+    max_seq_len: usize,
+}
+
+impl<B: Backend> AutoregressiveCache<B> {
+    pub fn new(max_seq_len: usize) -> Self {
+        Self {
+            cache: None,
+            max_seq_len,
+        }
+    }
+
+    /// Append `key`/`value` (for the newly processed tokens) to the cache and return the full
+    /// keys/values accumulated so far. If they exceed `max_seq_len`, the oldest positions are
+    /// dropped so only the most recent `max_seq_len` are kept.
+    pub fn forward(
+        &mut self,
+        key: Tensor<B, 4>,
+        value: Tensor<B, 4>,
+    ) -> (Tensor<B, 4>, Tensor<B, 4>) {
+        let (key, value) = match self.cache.take() {
+            Some((prev_key, prev_value)) => (
+                Tensor::cat(vec![prev_key, key], 2),
+                Tensor::cat(vec![prev_value, value], 2),
+            ),
+            None => (key, value),
+        };
+
+        let seq_len = key.dims()[2];
+        let (key, value) = if seq_len > self.max_seq_len {
+            let start = seq_len - self.max_seq_len;
+            (
+                key.narrow(2, start, self.max_seq_len),
+                value.narrow(2, start, self.max_seq_len),
+            )
+        } else {
+            (key, value)
+        };
+
+        self.cache = Some((key.clone(), value.clone()));
+        (key, value)
+    }
+
+    /// Number of tokens currently held in the cache.
+    pub fn len(&self) -> usize {
+        self.cache.as_ref().map(|(k, _)| k.dims()[2]).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop all cached state, e.g. when starting a new sequence.
+    pub fn reset(&mut self) {
+        self.cache = None;
+    }
+}
@@ -0,0 +1,349 @@
+//! Minimal reader for the GGUF container format used by llama.cpp-style quantized checkpoints:
+//! a header with tensor names/shapes/quantization types, followed by the aligned tensor blocks.
+//! See <https://github.com/ggerganov/ggml/blob/master/docs/gguf.md> for the on-disk layout.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+};
+
+const GGUF_MAGIC: u32 = 0x4655_4747; // "GGUF" little-endian
+
+#[derive(Debug)]
+pub enum GgufError {
+    Io(io::Error),
+    InvalidMagic(u32),
+    UnsupportedVersion(u32),
+    UnknownType(u32),
+    MissingTensor(String),
+}
+
+impl From<io::Error> for GgufError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// GGML tensor quantization types relevant to the checkpoints this crate loads. Block-quantized
+/// types pack `QK` values into a block with one or more scale/min factors; `block_size` below is
+/// the number of tensor elements represented by one on-disk block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GgmlType {
+    F32,
+    F16,
+    Q8_0,
+    Q4_0,
+}
+
+impl GgmlType {
+    fn from_u32(value: u32) -> Result<Self, GgufError> {
+        match value {
+            0 => Ok(Self::F32),
+            1 => Ok(Self::F16),
+            2 => Ok(Self::Q4_0),
+            8 => Ok(Self::Q8_0),
+            other => Err(GgufError::UnknownType(other)),
+        }
+    }
+
+    /// Number of elements represented by a single on-disk block.
+    fn block_size(self) -> usize {
+        match self {
+            Self::F32 | Self::F16 => 1,
+            Self::Q4_0 => 32,
+            Self::Q8_0 => 32,
+        }
+    }
+
+    /// Size in bytes of a single on-disk block.
+    fn block_bytes(self) -> usize {
+        match self {
+            Self::F32 => 4,
+            Self::F16 => 2,
+            // 1 f16 scale + 16 bytes, each packing two 4-bit values (32 in all).
+            Self::Q4_0 => 2 + 16,
+            // 1 f16 scale + 32 signed int8 values.
+            Self::Q8_0 => 2 + 32,
+        }
+    }
+}
+
+pub struct TensorInfo {
+    pub name: String,
+    pub shape: Vec<usize>,
+    pub dtype: GgmlType,
+    offset: u64,
+}
+
+/// A parsed GGUF file: string-keyed metadata plus the tensor table, with the data section left
+/// on disk and read/dequantized lazily via [`GgufFile::load_tensor`].
+pub struct GgufFile {
+    pub metadata: HashMap<String, String>,
+    pub tensors: Vec<TensorInfo>,
+    data_offset: u64,
+    path: std::path::PathBuf,
+}
+
+impl GgufFile {
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, GgufError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = File::open(&path)?;
+
+        let magic = read_u32(&mut file)?;
+        if magic != GGUF_MAGIC {
+            return Err(GgufError::InvalidMagic(magic));
+        }
+
+        let version = read_u32(&mut file)?;
+        if version != 2 && version != 3 {
+            return Err(GgufError::UnsupportedVersion(version));
+        }
+
+        let tensor_count = read_u64(&mut file)?;
+        let metadata_kv_count = read_u64(&mut file)?;
+
+        let mut metadata = HashMap::new();
+        for _ in 0..metadata_kv_count {
+            let key = read_gguf_string(&mut file)?;
+            let value = read_gguf_metadata_value(&mut file)?;
+            metadata.insert(key, value);
+        }
+
+        let mut tensors = Vec::with_capacity(tensor_count as usize);
+        for _ in 0..tensor_count {
+            let name = read_gguf_string(&mut file)?;
+            let n_dims = read_u32(&mut file)?;
+            let mut shape = Vec::with_capacity(n_dims as usize);
+            for _ in 0..n_dims {
+                shape.push(read_u64(&mut file)? as usize);
+            }
+            // GGUF stores the fastest-varying dimension first; burn's row-major tensors expect
+            // it last, so the shape is reversed once here and used consistently thereafter.
+            shape.reverse();
+
+            let dtype = GgmlType::from_u32(read_u32(&mut file)?)?;
+            let offset = read_u64(&mut file)?;
+
+            tensors.push(TensorInfo {
+                name,
+                shape,
+                dtype,
+                offset,
+            });
+        }
+
+        let alignment = metadata
+            .get("general.alignment")
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(32);
+
+        let unaligned_data_offset = file.stream_position()?;
+        let data_offset = unaligned_data_offset.div_ceil(alignment) * alignment;
+
+        Ok(Self {
+            metadata,
+            tensors,
+            data_offset,
+            path,
+        })
+    }
+
+    pub fn tensor(&self, name: &str) -> Result<&TensorInfo, GgufError> {
+        self.tensors
+            .iter()
+            .find(|t| t.name == name)
+            .ok_or_else(|| GgufError::MissingTensor(name.to_string()))
+    }
+
+    /// Read and fully dequantize a tensor to `f32`, returning it alongside its (burn-ordered)
+    /// shape.
+    pub fn load_tensor(&self, name: &str) -> Result<(Vec<f32>, Vec<usize>), GgufError> {
+        let info = self.tensor(name)?;
+        let num_elements: usize = info.shape.iter().product();
+        let num_blocks = num_elements.div_ceil(info.dtype.block_size());
+        let byte_len = num_blocks * info.dtype.block_bytes();
+
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.data_offset + info.offset))?;
+        let mut buf = vec![0u8; byte_len];
+        file.read_exact(&mut buf)?;
+
+        Ok((dequantize(&buf, info.dtype, num_elements), info.shape.clone()))
+    }
+}
+
+fn dequantize(data: &[u8], dtype: GgmlType, num_elements: usize) -> Vec<f32> {
+    match dtype {
+        GgmlType::F32 => data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect(),
+        GgmlType::F16 => data
+            .chunks_exact(2)
+            .map(|b| half::f16::from_le_bytes(b.try_into().unwrap()).to_f32())
+            .collect(),
+        GgmlType::Q8_0 => {
+            let mut out = Vec::with_capacity(num_elements);
+            for block in data.chunks_exact(dtype.block_bytes()) {
+                let scale = half::f16::from_le_bytes(block[0..2].try_into().unwrap()).to_f32();
+                for &q in &block[2..2 + 32] {
+                    out.push(q as i8 as f32 * scale);
+                }
+            }
+            out.truncate(num_elements);
+            out
+        }
+        GgmlType::Q4_0 => {
+            let mut out = Vec::with_capacity(num_elements);
+            for block in data.chunks_exact(dtype.block_bytes()) {
+                let scale = half::f16::from_le_bytes(block[0..2].try_into().unwrap()).to_f32();
+                let nibbles = &block[2..2 + 16];
+                // Low nibbles fill output positions 0..16, high nibbles fill 16..32 (not
+                // interleaved): `y[j] = lo(byte[j]) * d`, `y[j+16] = hi(byte[j]) * d`.
+                for &byte in nibbles {
+                    let lo = (byte & 0x0f) as i32 - 8;
+                    out.push(lo as f32 * scale);
+                }
+                for &byte in nibbles {
+                    let hi = ((byte >> 4) & 0x0f) as i32 - 8;
+                    out.push(hi as f32 * scale);
+                }
+            }
+            out.truncate(num_elements);
+            out
+        }
+    }
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_gguf_string(r: &mut impl Read) -> io::Result<String> {
+    let len = read_u64(r)? as usize;
+    let mut buf = vec![0u8; len];
+    r.read_exact(&mut buf)?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// Reads and discards/stringifies a metadata value. We only need string and integer metadata
+/// (e.g. `general.alignment`, context length) for loading, so every value is normalized to its
+/// string representation rather than modeling the full GGUF value union.
+fn read_gguf_metadata_value(r: &mut (impl Read + Seek)) -> Result<String, GgufError> {
+    let value_type = read_u32(r)?;
+    Ok(match value_type {
+        0 | 1 => (read_u8(r)? as i64).to_string(),      // UINT8 / INT8
+        2 | 3 => (read_u16(r)? as i64).to_string(),     // UINT16 / INT16
+        4 | 5 => (read_u32(r)? as i64).to_string(),     // UINT32 / INT32
+        6 => f32::from_bits(read_u32(r)?).to_string(),  // FLOAT32
+        7 => (read_u8(r)? != 0).to_string(),             // BOOL
+        8 => read_gguf_string(r)?,                       // STRING
+        9 => {
+            // ARRAY: element type + count, then that many elements. We skip over the contents
+            // since no array-typed metadata is needed by the loader today.
+            let elem_type = read_u32(r)?;
+            let count = read_u64(r)?;
+            for _ in 0..count {
+                read_gguf_metadata_value_of_type(r, elem_type)?;
+            }
+            String::new()
+        }
+        10 | 11 => (read_u64(r)? as i64).to_string(), // UINT64 / INT64
+        12 => f64::from_bits(read_u64(r)?).to_string(), // FLOAT64
+        other => return Err(GgufError::UnknownType(other)),
+    })
+}
+
+fn read_gguf_metadata_value_of_type(
+    r: &mut (impl Read + Seek),
+    value_type: u32,
+) -> Result<(), GgufError> {
+    match value_type {
+        0 | 1 => {
+            read_u8(r)?;
+        }
+        2 | 3 => {
+            read_u16(r)?;
+        }
+        4 | 5 | 6 => {
+            read_u32(r)?;
+        }
+        7 => {
+            read_u8(r)?;
+        }
+        8 => {
+            read_gguf_string(r)?;
+        }
+        10 | 11 | 12 => {
+            read_u64(r)?;
+        }
+        other => return Err(GgufError::UnknownType(other)),
+    }
+    Ok(())
+}
+
+fn read_u8(r: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0u8; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16(r: &mut impl Read) -> io::Result<u16> {
+    let mut buf = [0u8; 2];
+    r.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dequantize_q8_0_applies_block_scale() {
+        let scale = half::f16::from_f32(2.0);
+        let mut block = scale.to_le_bytes().to_vec();
+        block.extend((-16i8..16i8).map(|v| v as u8));
+
+        let out = dequantize(&block, GgmlType::Q8_0, 32);
+
+        assert_eq!(out.len(), 32);
+        assert_eq!(out[0], -32.0); // -16 * 2.0
+        assert_eq!(out[31], 30.0); // 15 * 2.0
+    }
+
+    #[test]
+    fn dequantize_q4_0_fills_low_nibbles_then_high_nibbles() {
+        // One block: scale 1.0, then 16 nibble-pair bytes. The first byte packs lo=0xa (value 2)
+        // and hi=0x9 (value 1); every other byte packs lo=hi=0x8 (value 0).
+        let scale = half::f16::from_f32(1.0);
+        let mut block = scale.to_le_bytes().to_vec();
+        block.push(0x9a);
+        block.extend(std::iter::repeat(0x88).take(15));
+
+        let out = dequantize(&block, GgmlType::Q4_0, 32);
+
+        assert_eq!(out.len(), 32);
+        // Low nibbles (lo(byte[j]) * scale) fill 0..16.
+        assert_eq!(out[0], 2.0);
+        assert!(out[1..16].iter().all(|&v| v == 0.0));
+        // High nibbles (hi(byte[j]) * scale) fill 16..32, not interleaved with the low nibbles.
+        assert_eq!(out[16], 1.0);
+        assert!(out[17..32].iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn dequantize_f32_passes_through() {
+        let data = 1.5f32.to_le_bytes();
+        let out = dequantize(&data, GgmlType::F32, 1);
+        assert_eq!(out, vec![1.5]);
+    }
+}
@@ -0,0 +1,8 @@
+pub mod gguf;
+pub mod llama;
+pub mod pretrained;
+pub mod sampling;
+pub mod tokenizer;
+
+mod cache;
+mod transformer;
@@ -0,0 +1,476 @@
+use burn::{
+    config::Config,
+    tensor::{backend::Backend, Device, Int, Tensor},
+};
+
+use crate::{
+    cache::AutoregressiveCache,
+    sampling::{FrequencyPresencePenalty, RepetitionPenalty, Sampler},
+    tokenizer::{Tokenizer, TokenOutputStream},
+    transformer::{TransformerLlama, TransformerLlamaConfig},
+};
+
+#[cfg(feature = "pretrained")]
+use crate::{
+    gguf::GgufFile,
+    pretrained::{Llama as PretrainedLlama, ModelMeta, QuantizedLlama},
+    tokenizer::{SentencePieceTokenizer, Tiktoken},
+};
+
+/// Configuration to create a Llama model.
+#[derive(Config, Debug)]
+pub struct LlamaConfig {
+    pub tokenizer: String,
+    pub max_seq_len: usize,
+    pub transformer: TransformerLlamaConfig,
+}
+
+impl LlamaConfig {
+    pub fn init<B: Backend, T: Tokenizer>(
+        &self,
+        tokenizer: T,
+        device: &Device<B>,
+    ) -> Llama<B, T> {
+        self.init_with_model(tokenizer, self.transformer.init(device), device)
+    }
+
+    fn init_with_model<B: Backend, T: Tokenizer>(
+        &self,
+        tokenizer: T,
+        model: TransformerLlama<B>,
+        device: &Device<B>,
+    ) -> Llama<B, T> {
+        let cache = (0..self.transformer.num_layers)
+            .map(|_| AutoregressiveCache::new(self.max_seq_len))
+            .collect();
+
+        Llama {
+            tokenizer,
+            model,
+            cache,
+            context: Vec::new(),
+            device: device.clone(),
+        }
+    }
+
+    #[cfg(feature = "pretrained")]
+    pub fn tiny_llama_pretrained<B: Backend>(
+        device: &Device<B>,
+    ) -> Result<Llama<B, SentencePieceTokenizer>, String> {
+        let model = PretrainedLlama::TinyLlama.pretrained();
+        let tokenizer_path = model.download_tokenizer(None).map_err(|e| e.to_string())?;
+        let _weights_path = model.download_weights(None).map_err(|e| e.to_string())?;
+
+        let tokenizer = SentencePieceTokenizer::new(tokenizer_path.to_str().unwrap())?;
+
+        Self {
+            tokenizer: tokenizer_path.to_str().unwrap().into(),
+            max_seq_len: 4096,
+            transformer: TransformerLlamaConfig::new(32000, 2048, 5632, 22, 32, 4, 4096),
+        }
+        .init(tokenizer, device)
+        .pipe(Ok)
+    }
+
+    /// `hf_token` (falling back to the `HF_TOKEN` environment variable) is required since the
+    /// Llama-3 repo is gated on Hugging Face Hub.
+    #[cfg(feature = "pretrained")]
+    pub fn llama3_8b_pretrained<B: Backend>(
+        _instruct: bool,
+        hf_token: Option<&str>,
+        device: &Device<B>,
+    ) -> Result<Llama<B, Tiktoken>, String> {
+        let model = PretrainedLlama::Llama3.pretrained();
+        let tokenizer_path = model.download_tokenizer(hf_token).map_err(|e| e.to_string())?;
+        let _weights_path = model.download_weights(hf_token).map_err(|e| e.to_string())?;
+
+        let tokenizer = Tiktoken::new(tokenizer_path.to_str().unwrap())?;
+
+        Self {
+            tokenizer: tokenizer_path.to_str().unwrap().into(),
+            max_seq_len: 8192,
+            transformer: TransformerLlamaConfig::new(128256, 4096, 14336, 32, 32, 8, 8192)
+                .with_rope_theta(500000.0),
+        }
+        .init(tokenizer, device)
+        .pipe(Ok)
+    }
+
+    /// Like [`Self::tiny_llama_pretrained`], but loads the quantized GGUF checkpoint instead of
+    /// the full-precision `model.bin`, dequantizing tensors on load.
+    #[cfg(feature = "pretrained")]
+    pub fn tiny_llama_pretrained_gguf<B: Backend>(
+        device: &Device<B>,
+    ) -> Result<Llama<B, SentencePieceTokenizer>, String> {
+        let pretrained = QuantizedLlama::TinyLlamaChatQ8_0.pretrained();
+        let tokenizer_path = pretrained.download_tokenizer(None).map_err(|e| e.to_string())?;
+        let weights_path = pretrained.download_weights(None).map_err(|e| e.to_string())?;
+
+        let tokenizer = SentencePieceTokenizer::new(tokenizer_path.to_str().unwrap())?;
+        let gguf = GgufFile::open(&weights_path).map_err(|e| format!("{e:?}"))?;
+
+        let config = Self {
+            tokenizer: tokenizer_path.to_str().unwrap().into(),
+            max_seq_len: 4096,
+            transformer: TransformerLlamaConfig::new(32000, 2048, 5632, 22, 32, 4, 4096),
+        };
+        let model = TransformerLlama::from_gguf(&gguf, &config.transformer, device)
+            .map_err(|e| format!("{e:?}"))?;
+
+        Ok(config.init_with_model(tokenizer, model, device))
+    }
+
+    /// Like [`Self::llama3_8b_pretrained`], but loads the quantized GGUF checkpoint instead of
+    /// the full-precision `model.bin`, dequantizing tensors on load. See
+    /// [`Self::llama3_8b_pretrained`] for `hf_token`.
+    #[cfg(feature = "pretrained")]
+    pub fn llama3_8b_pretrained_gguf<B: Backend>(
+        hf_token: Option<&str>,
+        device: &Device<B>,
+    ) -> Result<Llama<B, Tiktoken>, String> {
+        let pretrained = QuantizedLlama::Llama3InstructQ4_0.pretrained();
+        let tokenizer_path = pretrained.download_tokenizer(hf_token).map_err(|e| e.to_string())?;
+        let weights_path = pretrained.download_weights(hf_token).map_err(|e| e.to_string())?;
+
+        let tokenizer = Tiktoken::new(tokenizer_path.to_str().unwrap())?;
+        let gguf = GgufFile::open(&weights_path).map_err(|e| format!("{e:?}"))?;
+
+        let config = Self {
+            tokenizer: tokenizer_path.to_str().unwrap().into(),
+            max_seq_len: 8192,
+            transformer: TransformerLlamaConfig::new(128256, 4096, 14336, 32, 32, 8, 8192)
+                .with_rope_theta(500000.0),
+        };
+        let model = TransformerLlama::from_gguf(&gguf, &config.transformer, device)
+            .map_err(|e| format!("{e:?}"))?;
+
+        Ok(config.init_with_model(tokenizer, model, device))
+    }
+
+    /// Load a Llama checkpoint from an arbitrary Hugging Face Hub repo/revision, e.g. a user's
+    /// own fine-tune, rather than one of the baked-in [`crate::pretrained::Llama`] variants.
+    /// `repo_id`/`revision`/`model_file`/`tokenizer_file` take `&str` rather than `&'static str`
+    /// so they can come from a runtime value (a CLI flag, a config file) rather than only a
+    /// string literal. `load_tokenizer` builds the tokenizer
+    /// (`SentencePieceTokenizer::new`/`Tiktoken::new` for the built-in model families) from the
+    /// downloaded tokenizer file's path.
+    #[cfg(feature = "pretrained")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_hf_hub<B: Backend, T: Tokenizer>(
+        repo_id: &str,
+        revision: &str,
+        model_file: &str,
+        tokenizer_file: &str,
+        hf_token: Option<&str>,
+        transformer: TransformerLlamaConfig,
+        load_tokenizer: impl FnOnce(&str) -> Result<T, String>,
+        device: &Device<B>,
+    ) -> Result<Llama<B, T>, String> {
+        let pretrained = crate::pretrained::Pretrained::new(repo_id, revision, model_file, tokenizer_file);
+        let tokenizer_path = pretrained.download_tokenizer(hf_token).map_err(|e| e.to_string())?;
+        let _weights_path = pretrained.download_weights(hf_token).map_err(|e| e.to_string())?;
+
+        let tokenizer = load_tokenizer(tokenizer_path.to_str().unwrap())?;
+        let max_seq_len = transformer.max_seq_len;
+
+        Self {
+            tokenizer: tokenizer_path.to_str().unwrap().into(),
+            max_seq_len,
+            transformer,
+        }
+        .init(tokenizer, device)
+        .pipe(Ok)
+    }
+}
+
+// Small local extension so the pretrained constructors above can read as a single expression,
+// matching the rest of this file's style.
+trait Pipe: Sized {
+    fn pipe<R>(self, f: impl FnOnce(Self) -> R) -> R {
+        f(self)
+    }
+}
+impl<T> Pipe for T {}
+
+/// Options controlling a single [`Llama::generate_stream`] call, beyond the core
+/// prompt/length/temperature/sampler arguments.
+#[derive(Default)]
+pub struct GenerateOptions<'a> {
+    pub repetition_penalty: Option<&'a RepetitionPenalty>,
+    pub frequency_presence_penalty: Option<&'a FrequencyPresencePenalty>,
+    /// Stop generation as soon as the decoded output contains any of these strings; the matched
+    /// stop sequence itself is trimmed from the returned/emitted text.
+    pub stop_strings: &'a [String],
+    /// Keep generating past the tokenizer's EOS / end-of-turn token id(s) instead of stopping.
+    pub ignore_eos: bool,
+}
+
+fn find_stop_string(text: &str, stop_strings: &[String]) -> Option<usize> {
+    stop_strings.iter().filter_map(|stop| text.find(stop.as_str())).min()
+}
+
+/// The largest char-boundary index in `text` that leaves at least `hold_back` trailing bytes
+/// unconsumed, i.e. the most that can safely be emitted without risking a future token
+/// completing a stop-string match that straddles the cut point.
+fn safe_emit_boundary(text: &str, hold_back: usize) -> usize {
+    let target = text.len().saturating_sub(hold_back);
+    (0..=target).rev().find(|&i| text.is_char_boundary(i)).unwrap_or(0)
+}
+
+/// Generated text along with basic throughput stats.
+pub struct GenerationOutput {
+    pub text: String,
+    pub tokens: usize,
+    pub time: f64,
+}
+
+/// Strategy for turning a sequence's per-token hidden states into a single embedding vector, used
+/// by [`Llama::embed`].
+pub enum Pooling {
+    /// Average the final hidden state across every token in the sequence.
+    Mean,
+    /// Use only the final hidden state of the last token.
+    LastToken,
+}
+
+/// A loaded Llama model, ready to run autoregressive generation.
+///
+/// The KV cache and the running token history (`context`) persist across calls to
+/// [`Llama::generate`]/[`Llama::generate_stream`], so a multi-turn caller (see
+/// `examples/chat.rs`'s `--interactive` mode) only has to pass the newly appended turn's text on
+/// each call instead of re-encoding and re-prefilling the whole conversation. Call
+/// [`Llama::reset`] to start an unrelated sequence from scratch.
+pub struct Llama<B: Backend, T: Tokenizer> {
+    pub tokenizer: T,
+    pub model: TransformerLlama<B>,
+    cache: Vec<AutoregressiveCache<B>>,
+    context: Vec<u32>,
+    device: Device<B>,
+}
+
+impl<B: Backend, T: Tokenizer> Llama<B, T> {
+    /// Generate up to `sample_len` new tokens from `prompt`, blocking until the full text is
+    /// ready. Generation stops early at the tokenizer's EOS / end-of-turn token.
+    pub fn generate(
+        &mut self,
+        prompt: &str,
+        sample_len: usize,
+        temperature: f64,
+        sampler: &mut Sampler,
+    ) -> GenerationOutput {
+        let mut text = String::new();
+        let now = std::time::Instant::now();
+        let num_tokens = self.generate_stream(
+            prompt,
+            sample_len,
+            temperature,
+            sampler,
+            &GenerateOptions::default(),
+            |fragment| text.push_str(fragment),
+        );
+
+        GenerationOutput {
+            text,
+            tokens: num_tokens,
+            time: now.elapsed().as_secs_f64(),
+        }
+    }
+
+    /// Generate up to `sample_len` new tokens from `prompt`, invoking `callback` with each newly
+    /// decoded text fragment as soon as it's available rather than only returning once
+    /// generation has completed. Generation stops early on a stop string or (unless
+    /// `options.ignore_eos`) an EOS / end-of-turn token. Returns the number of tokens generated.
+    ///
+    /// Detokenization is incremental and UTF-8-safe: see [`TokenOutputStream`] for how a
+    /// generated token id is only flushed to `callback` once it no longer straddles a
+    /// multi-byte codepoint boundary.
+    pub fn generate_stream(
+        &mut self,
+        prompt: &str,
+        sample_len: usize,
+        temperature: f64,
+        sampler: &mut Sampler,
+        options: &GenerateOptions,
+        mut callback: impl FnMut(&str),
+    ) -> usize {
+        // Only the tokens not yet seen by the KV cache are fed to the model: the full prompt on
+        // the first call (or the first call after `reset`), then a single token per subsequent
+        // step. Resuming a session (`examples/chat.rs --interactive`) lands here with `context`
+        // already holding prior turns, so `bos` is only requested once per sequence.
+        let mut feed = self.tokenizer.encode(prompt, self.context.is_empty(), false);
+        let mut stream = TokenOutputStream::new(&self.tokenizer);
+        // Accumulated text generated this call, checked against `options.stop_strings` after
+        // every fragment since a stop string can span more than one token (or more than one
+        // decoded fragment). `emitted_len` is how much of it has already reached `callback`;
+        // the rest is held back since it could still turn out to be a prefix of a stop match.
+        let mut generated_text = String::new();
+        let mut emitted_len = 0;
+        let hold_back = options.stop_strings.iter().map(|s| s.len()).max().unwrap_or(0).saturating_sub(1);
+        let mut stopped_on_stop_string = false;
+        let mut num_tokens = 0;
+
+        for _ in 0..sample_len {
+            let input = Tensor::<B, 1, Int>::from_ints(feed.as_slice(), &self.device).unsqueeze();
+            let logits = self.model.forward(input, &mut self.cache);
+            self.context.extend_from_slice(&feed);
+
+            let [_, seq_len, _] = logits.dims();
+            let last_row = logits.slice([0..1, seq_len - 1..seq_len]).squeeze::<2>(1);
+
+            // Penalties only look at the tokens generated by this call, not the prompt.
+            let last_row = match options.repetition_penalty {
+                Some(penalty) => penalty.apply(last_row, stream.tokens()),
+                None => last_row,
+            };
+            let last_row = match options.frequency_presence_penalty {
+                Some(penalty) => penalty.apply(last_row, stream.tokens()),
+                None => last_row,
+            };
+
+            let probs = if temperature > 0.0 {
+                burn::tensor::activation::softmax(last_row / temperature, 1)
+            } else {
+                last_row
+            };
+
+            let next_token = sampler.sample(probs).into_scalar().elem::<i64>() as u32;
+            num_tokens += 1;
+            feed = vec![next_token];
+
+            if let Some(fragment) = stream.next_token(next_token) {
+                generated_text.push_str(&fragment);
+
+                match find_stop_string(&generated_text, options.stop_strings) {
+                    Some(stop_at) => {
+                        if stop_at > emitted_len {
+                            callback(&generated_text[emitted_len..stop_at]);
+                        }
+                        emitted_len = generated_text.len();
+                        stopped_on_stop_string = true;
+                    }
+                    None => {
+                        // Only flush text that's far enough from the end that it can no longer
+                        // become part of a stop-string match once more tokens arrive.
+                        let boundary = safe_emit_boundary(&generated_text, hold_back);
+                        if boundary > emitted_len {
+                            callback(&generated_text[emitted_len..boundary]);
+                            emitted_len = boundary;
+                        }
+                    }
+                }
+            }
+
+            let is_eos = next_token == self.tokenizer.eos_id()
+                || self.tokenizer.stop_ids().contains(&next_token);
+            if stopped_on_stop_string || (is_eos && !options.ignore_eos) {
+                break;
+            }
+        }
+
+        // The token sampled on the last iteration is always left un-fed (the loop defers
+        // feeding a token to the *next* iteration's forward pass), so one last forward call
+        // flushes it into the cache. This keeps `context`/the cache in sync for a later call
+        // that continues this same session, e.g. the next turn of an interactive chat.
+        if !feed.is_empty() {
+            let input = Tensor::<B, 1, Int>::from_ints(feed.as_slice(), &self.device).unsqueeze();
+            self.model.forward(input, &mut self.cache);
+            self.context.extend_from_slice(&feed);
+        }
+
+        if let Some(fragment) = stream.flush() {
+            generated_text.push_str(&fragment);
+        }
+
+        // Generation is over, so nothing held back for stop-string safety can still match one;
+        // flush whatever's left (a no-op if we already broke on a stop-string match above).
+        if !stopped_on_stop_string && generated_text.len() > emitted_len {
+            callback(&generated_text[emitted_len..]);
+        }
+
+        num_tokens
+    }
+
+    /// Reset the KV cache and conversation history, e.g. before starting a new, unrelated
+    /// prompt. Subsequent calls to [`Self::generate`]/[`Self::generate_stream`] will prefill
+    /// from scratch (including the BOS token) rather than continuing the previous sequence.
+    pub fn reset(&mut self) {
+        for cache in self.cache.iter_mut() {
+            cache.reset();
+        }
+        self.context.clear();
+    }
+
+    /// Embed `text` into a single vector, for retrieval/semantic-search use cases rather than
+    /// autoregressive generation. Runs a single forward pass through the transformer body
+    /// (stopping before the LM head, see [`TransformerLlama::forward_hidden`]) and pools the
+    /// resulting per-token hidden states with `pooling`, optionally L2-normalizing the result.
+    ///
+    /// This is a self-contained forward pass: it runs against a fresh, temporary KV cache and
+    /// leaves the model's own KV cache/conversation history untouched, so it can be interleaved
+    /// with [`Self::generate`]/[`Self::generate_stream`] calls without disturbing an in-progress
+    /// multi-turn session.
+    pub fn embed(&mut self, text: &str, pooling: Pooling, normalize: bool) -> Tensor<B, 1> {
+        let tokens = self.tokenizer.encode(text, true, false);
+        let seq_len = tokens.len();
+        let mut cache: Vec<_> = (0..self.cache.len())
+            .map(|_| AutoregressiveCache::new(seq_len))
+            .collect();
+        let input = Tensor::<B, 1, Int>::from_ints(tokens.as_slice(), &self.device).unsqueeze();
+        let hidden = self.model.forward_hidden(input, &mut cache);
+
+        let pooled = match pooling {
+            Pooling::Mean => hidden.mean_dim(1).squeeze::<2>(1),
+            Pooling::LastToken => hidden.slice([0..1, seq_len - 1..seq_len]).squeeze::<2>(1),
+        }
+        .squeeze::<1>(0);
+
+        if normalize {
+            let norm = pooled.clone().powf_scalar(2.0).sum().sqrt();
+            pooled / norm
+        } else {
+            pooled
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stops(strs: &[&str]) -> Vec<String> {
+        strs.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn find_stop_string_returns_earliest_match() {
+        let stop_strings = stops(&["world", "hello"]);
+        assert_eq!(find_stop_string("say hello world", &stop_strings), Some(4));
+    }
+
+    #[test]
+    fn find_stop_string_none_when_absent() {
+        let stop_strings = stops(&["bye"]);
+        assert_eq!(find_stop_string("hello world", &stop_strings), None);
+    }
+
+    #[test]
+    fn safe_emit_boundary_holds_back_requested_bytes() {
+        assert_eq!(safe_emit_boundary("hello world", 5), "hello ".len());
+    }
+
+    #[test]
+    fn safe_emit_boundary_never_splits_a_multibyte_char() {
+        // "é" is 2 bytes; holding back 1 byte must not cut inside it, so the boundary rounds
+        // down to the char before it instead.
+        let text = "a é";
+        let hold_back = 1;
+        let boundary = safe_emit_boundary(text, hold_back);
+        assert!(text.is_char_boundary(boundary));
+        assert_eq!(&text[..boundary], "a ");
+    }
+
+    #[test]
+    fn safe_emit_boundary_clamps_to_zero_when_hold_back_exceeds_len() {
+        assert_eq!(safe_emit_boundary("hi", 10), 0);
+    }
+}
@@ -1,59 +1,179 @@
-/// Pre-trained model metadata.
+/// Pre-trained model metadata: a Hugging Face Hub repo and revision for the model weights, and
+/// (since the tokenizer isn't always bundled in the same repo, e.g. quantized GGUF mirrors) a
+/// separate repo and revision for the tokenizer.
 pub struct Pretrained {
-    pub(super) name: &'static str,
-    pub(super) model: &'static str,
-    pub(super) tokenizer: &'static str,
+    pub(super) repo_id: String,
+    pub(super) revision: String,
+    pub(super) model_file: String,
+    pub(super) tokenizer_repo_id: String,
+    pub(super) tokenizer_revision: String,
+    pub(super) tokenizer_file: String,
+}
+
+impl Pretrained {
+    /// Point at an arbitrary Hugging Face Hub repo/revision, e.g. a user's own fine-tuned Llama
+    /// checkpoint, rather than one of the two baked-in models. Weights and tokenizer are
+    /// expected to live in the same repo; use [`Self::with_tokenizer_from`] if not. Takes
+    /// `impl Into<String>` rather than `&'static str` so callers can build a repo id/revision at
+    /// runtime (e.g. from a CLI flag) instead of only from a string literal.
+    pub fn new(
+        repo_id: impl Into<String>,
+        revision: impl Into<String>,
+        model_file: impl Into<String>,
+        tokenizer_file: impl Into<String>,
+    ) -> Self {
+        let repo_id = repo_id.into();
+        let revision = revision.into();
+        Self {
+            tokenizer_repo_id: repo_id.clone(),
+            tokenizer_revision: revision.clone(),
+            repo_id,
+            revision,
+            model_file: model_file.into(),
+            tokenizer_file: tokenizer_file.into(),
+        }
+    }
+
+    /// Override where the tokenizer is downloaded from, for repos (e.g. third-party GGUF
+    /// mirrors) that don't bundle one alongside the weights.
+    pub fn with_tokenizer_from(
+        mut self,
+        repo_id: impl Into<String>,
+        revision: impl Into<String>,
+    ) -> Self {
+        self.tokenizer_repo_id = repo_id.into();
+        self.tokenizer_revision = revision.into();
+        self
+    }
 }
 
 #[cfg(feature = "pretrained")]
 mod downloader {
     use super::*;
-    use burn::data::network::downloader;
-    use std::fs::{create_dir_all, File};
-    use std::io::Write;
-    use std::path::PathBuf;
+    use std::{
+        fs::{create_dir_all, read_to_string, write, File},
+        io::{Read, Write as _},
+        path::PathBuf,
+    };
 
-    impl Pretrained {
-        /// Download the file to the local cache directory.
-        fn download(&self, url: &str) -> Result<PathBuf, std::io::Error> {
-            // Model cache directory
-            let model_dir = dirs::home_dir()
-                .expect("Should be able to get home directory")
-                .join(".cache")
-                .join("llama-burn")
-                .join(self.name);
-
-            if !model_dir.exists() {
-                create_dir_all(&model_dir)?;
-            }
+    const HF_ENDPOINT: &str = "https://huggingface.co";
+
+    /// Explicit token if given, otherwise `HF_TOKEN` from the environment (gated repos, e.g.
+    /// Llama-3, require one).
+    fn resolve_token(explicit: Option<&str>) -> Option<String> {
+        explicit
+            .map(str::to_string)
+            .or_else(|| std::env::var("HF_TOKEN").ok())
+    }
+
+    fn auth_header(token: &Option<String>) -> Option<String> {
+        token.as_ref().map(|t| format!("Bearer {t}"))
+    }
+
+    /// Local cache directory for a repo+revision, following the standard Hugging Face Hub cache
+    /// layout (`<cache>/models--org--name/snapshots/<revision>/`), so re-runs against the same
+    /// repo/revision share a cache no matter which `Pretrained` value referenced it.
+    fn cache_dir(repo_id: &str, revision: &str) -> PathBuf {
+        dirs::home_dir()
+            .expect("Should be able to get home directory")
+            .join(".cache")
+            .join("llama-burn")
+            .join(format!("models--{}", repo_id.replace('/', "--")))
+            .join("snapshots")
+            .join(revision)
+    }
+
+    /// Download `filename` from `repo_id`@`revision` to the local cache, skipping the download
+    /// if a cached copy already matches the remote file's current ETag.
+    fn download(
+        repo_id: &str,
+        revision: &str,
+        filename: &str,
+        token: Option<&str>,
+    ) -> Result<PathBuf, std::io::Error> {
+        let cache_dir = cache_dir(repo_id, revision);
+        if !cache_dir.exists() {
+            create_dir_all(&cache_dir)?;
+        }
+
+        let file_path = cache_dir.join(filename);
+        let etag_path = cache_dir.join(format!("{filename}.etag"));
+        let url = format!("{HF_ENDPOINT}/{repo_id}/resolve/{revision}/{filename}");
+        let token = resolve_token(token);
+
+        let remote_etag = fetch_etag(&url, &token);
+        let cached_etag = read_to_string(&etag_path).ok();
+
+        // If the ETag can't be confirmed (the HEAD request failed, or the offline/flaky
+        // network the request came from), fall back to trusting whatever's already cached
+        // rather than forcing a re-download of a potentially multi-GB file.
+        let up_to_date = file_path.exists()
+            && match &remote_etag {
+                Some(_) => remote_etag == cached_etag,
+                None => true,
+            };
+
+        if !up_to_date {
+            let bytes = download_bytes(&url, &token)?;
+            File::create(&file_path)?.write_all(&bytes)?; // write_all is not OS limited (files over 2GB)
 
-            let file_base_name = url
-                .rsplit_once('/')
-                .unwrap()
-                .1
-                .replace("?download=true", "");
-            let file_name = model_dir.join(&file_base_name);
-            if !file_name.exists() {
-                // Download file content
-                let bytes = downloader::download_file_as_bytes(url, &file_base_name);
-
-                // Write content to file
-                let mut output_file = File::create(&file_name)?;
-                output_file.write_all(&bytes)?; // write_all is not OS limited (files over 2GB)
+            if let Some(etag) = &remote_etag {
+                write(&etag_path, etag)?;
             }
+        }
+
+        Ok(file_path)
+    }
+
+    impl Pretrained {
+        /// Download the pre-trained model weights to the local cache directory. `hf_token`
+        /// overrides `HF_TOKEN` from the environment, needed for gated repos such as Llama-3.
+        pub fn download_weights(&self, hf_token: Option<&str>) -> Result<PathBuf, std::io::Error> {
+            download(&self.repo_id, &self.revision, &self.model_file, hf_token)
+        }
 
-            Ok(file_name)
+        /// Download the tokenizer to the local cache directory. See [`Self::download_weights`]
+        /// for `hf_token`.
+        pub fn download_tokenizer(&self, hf_token: Option<&str>) -> Result<PathBuf, std::io::Error> {
+            download(
+                &self.tokenizer_repo_id,
+                &self.tokenizer_revision,
+                &self.tokenizer_file,
+                hf_token,
+            )
         }
+    }
 
-        /// Download the pre-trained model weights to the local cache directory.
-        pub fn download_weights(&self) -> Result<PathBuf, std::io::Error> {
-            self.download(self.model)
+    fn io_error(message: impl ToString) -> std::io::Error {
+        std::io::Error::other(message.to_string())
+    }
+
+    fn fetch_etag(url: &str, token: &Option<String>) -> Option<String> {
+        let mut request = ureq::head(url);
+        if let Some(auth) = auth_header(token) {
+            request = request.set("Authorization", &auth);
         }
 
-        /// Download the tokenizer to the local cache directory.
-        pub fn download_tokenizer(&self) -> Result<PathBuf, std::io::Error> {
-            self.download(self.tokenizer)
+        request
+            .call()
+            .ok()?
+            .header("etag")
+            .map(|etag| etag.trim_matches('"').to_string())
+    }
+
+    fn download_bytes(url: &str, token: &Option<String>) -> Result<Vec<u8>, std::io::Error> {
+        let mut request = ureq::get(url);
+        if let Some(auth) = auth_header(token) {
+            request = request.set("Authorization", &auth);
         }
+
+        let response = request.call().map_err(io_error)?;
+        let mut bytes = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut bytes)
+            .map_err(io_error)?;
+        Ok(bytes)
     }
 }
 
@@ -72,16 +192,51 @@ pub enum Llama {
 impl ModelMeta for Llama {
     fn pretrained(&self) -> Pretrained {
         match self {
-            Self::Llama3 => Pretrained {
-                name: "Llama-3-8B",
-                model: "https://huggingface.co/tracel-ai/llama-3-8b-burn/resolve/main/model.bin?download=true",
-                tokenizer: "https://huggingface.co/tracel-ai/llama-3-8b-burn/resolve/main/tokenizer.model?download=true",
-            },
-            Self::TinyLlama => Pretrained {
-                name: "TinyLlama-1.1B",
-                model: "https://huggingface.co/tracel-ai/tiny-llama-1.1b-burn/resolve/main/model.bin?download=true",
-                tokenizer: "https://huggingface.co/tracel-ai/tiny-llama-1.1b-burn/resolve/main/tokenizer.json?download=true",
-            },
+            Self::Llama3 => Pretrained::new(
+                "tracel-ai/llama-3-8b-burn",
+                "main",
+                "model.bin",
+                "tokenizer.model",
+            ),
+            Self::TinyLlama => Pretrained::new(
+                "tracel-ai/tiny-llama-1.1b-burn",
+                "main",
+                "model.bin",
+                "tokenizer.json",
+            ),
+        }
+    }
+}
+
+/// Quantized (GGUF) Llama pre-trained weights, for running the larger models on consumer
+/// hardware. The tokenizer is still downloaded as a standalone file; only the model weights
+/// come from the GGUF checkpoint.
+pub enum QuantizedLlama {
+    /// Llama-3-8B-Instruct, 4-bit (Q4_0) quantization. Note this is the plain Q4_0 checkpoint,
+    /// not a K-quant: [`crate::gguf::GgmlType`] only dequantizes F32/F16/Q8_0/Q4_0, so a Q4_K_M
+    /// (or other K-quant) checkpoint would fail to load.
+    Llama3InstructQ4_0,
+    /// TinyLlama-1.1B-Chat, 8-bit (Q8_0) quantization.
+    TinyLlamaChatQ8_0,
+}
+
+impl ModelMeta for QuantizedLlama {
+    fn pretrained(&self) -> Pretrained {
+        match self {
+            Self::Llama3InstructQ4_0 => Pretrained::new(
+                "QuantFactory/Meta-Llama-3-8B-Instruct-GGUF",
+                "main",
+                "Meta-Llama-3-8B-Instruct.Q4_0.gguf",
+                "tokenizer.model",
+            )
+            .with_tokenizer_from("tracel-ai/llama-3-8b-burn", "main"),
+            Self::TinyLlamaChatQ8_0 => Pretrained::new(
+                "TheBloke/TinyLlama-1.1B-Chat-v1.0-GGUF",
+                "main",
+                "tinyllama-1.1b-chat-v1.0.Q8_0.gguf",
+                "tokenizer.json",
+            )
+            .with_tokenizer_from("tracel-ai/tiny-llama-1.1b-burn", "main"),
         }
     }
 }
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use burn::tensor::{backend::Backend, Int, Tensor};
+use rand::{rngs::StdRng, SeedableRng};
+
+/// A token sampling strategy, applied to the token probabilities (the softmax of the
+/// temperature-scaled logits) of the last position in the sequence.
+pub trait Sampling {
+    fn sample<B: Backend>(&mut self, logits: Tensor<B, 2>) -> Tensor<B, 2, Int>;
+}
+
+/// Sampling strategies supported by [`Llama::generate`](crate::llama::Llama::generate).
+pub enum Sampler {
+    Argmax,
+    TopK(TopK),
+    TopP(TopP),
+    TopKThenTopP(TopKThenTopP),
+    MinP(MinP),
+}
+
+impl Sampler {
+    pub fn sample<B: Backend>(&mut self, logits: Tensor<B, 2>) -> Tensor<B, 2, Int> {
+        match self {
+            Self::Argmax => logits.argmax(1),
+            Self::TopK(s) => s.sample(logits),
+            Self::TopP(s) => s.sample(logits),
+            Self::TopKThenTopP(s) => s.sample(logits),
+            Self::MinP(s) => s.sample(logits),
+        }
+    }
+}
+
+/// Top-k sampling: restricts the candidate set to the `k` highest probability tokens, then
+/// samples from it.
+pub struct TopK {
+    k: usize,
+    rng: StdRng,
+}
+
+impl TopK {
+    pub fn new(k: usize, seed: u64) -> Self {
+        Self {
+            k,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Sampling for TopK {
+    fn sample<B: Backend>(&mut self, probs: Tensor<B, 2>) -> Tensor<B, 2, Int> {
+        let device = probs.device();
+        let (probs_sort, probs_idx) = probs.sort_descending_with_indices(1);
+
+        let k = self.k.min(probs_sort.dims()[1]);
+        let probs_sort = probs_sort.narrow(1, 0, k);
+        let probs_idx = probs_idx.narrow(1, 0, k);
+
+        let next_token_idx = sample_multinomial(probs_sort, &mut self.rng, &device);
+        probs_idx.gather(1, next_token_idx)
+    }
+}
+
+/// Top-k followed by nucleus sampling: narrows to the `k` highest probability tokens first,
+/// then further restricts to the smallest prefix of those whose cumulative probability exceeds
+/// `p`, before sampling.
+pub struct TopKThenTopP {
+    k: usize,
+    p: f64,
+    rng: StdRng,
+}
+
+impl TopKThenTopP {
+    pub fn new(k: usize, p: f64, seed: u64) -> Self {
+        Self {
+            k,
+            p,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Sampling for TopKThenTopP {
+    fn sample<B: Backend>(&mut self, probs: Tensor<B, 2>) -> Tensor<B, 2, Int> {
+        let device = probs.device();
+        let (probs_sort, probs_idx) = probs.sort_descending_with_indices(1);
+
+        let k = self.k.min(probs_sort.dims()[1]);
+        let probs_sort = probs_sort.narrow(1, 0, k);
+        let probs_idx = probs_idx.narrow(1, 0, k);
+
+        let probs_sort_cumsum = probs_sort.clone().cumsum(1);
+        let mask = probs_sort_cumsum.sub(probs_sort.clone()).greater_elem(self.p);
+        let probs_sort = probs_sort.mask_fill(mask, 0.0);
+
+        let next_token_idx = sample_multinomial(probs_sort, &mut self.rng, &device);
+        probs_idx.gather(1, next_token_idx)
+    }
+}
+
+/// Min-p sampling: keeps only tokens whose probability is at least `p * max_prob`, then samples
+/// from them. Adapts the candidate set to how peaked the distribution is, unlike top-k/top-p.
+pub struct MinP {
+    p: f64,
+    rng: StdRng,
+}
+
+impl MinP {
+    pub fn new(p: f64, seed: u64) -> Self {
+        Self {
+            p,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Sampling for MinP {
+    fn sample<B: Backend>(&mut self, probs: Tensor<B, 2>) -> Tensor<B, 2, Int> {
+        let device = probs.device();
+        let (probs_sort, probs_idx) = probs.sort_descending_with_indices(1);
+
+        let batch = probs_sort.dims()[0];
+        let max_prob = probs_sort.clone().slice([0..batch, 0..1]);
+        let threshold = max_prob.mul_scalar(self.p);
+        let mask = probs_sort.clone().lower(threshold);
+        let probs_sort = probs_sort.mask_fill(mask, 0.0);
+
+        let next_token_idx = sample_multinomial(probs_sort, &mut self.rng, &device);
+        probs_idx.gather(1, next_token_idx)
+    }
+}
+
+/// Divides the logits of previously-seen tokens by `penalty` (multiplies if negative), which
+/// discourages the model from repeating itself when `penalty > 1.0`. Only the last `last_n`
+/// generated tokens are considered.
+pub struct RepetitionPenalty {
+    pub penalty: f64,
+    pub last_n: usize,
+}
+
+impl RepetitionPenalty {
+    pub fn new(penalty: f64, last_n: usize) -> Self {
+        Self { penalty, last_n }
+    }
+
+    pub fn apply<B: Backend>(&self, logits: Tensor<B, 2>, generated: &[u32]) -> Tensor<B, 2> {
+        if self.penalty == 1.0 {
+            return logits;
+        }
+
+        let device = logits.device();
+        let [batch, vocab_size] = logits.dims();
+        let mut data: Vec<f32> = logits.into_data().convert::<f32>().to_vec().unwrap();
+
+        let start = generated.len().saturating_sub(self.last_n);
+        let mut seen = std::collections::HashSet::new();
+        for &token in &generated[start..] {
+            let idx = token as usize;
+            if idx < vocab_size && seen.insert(idx) {
+                let score = data[idx];
+                data[idx] = if score > 0.0 {
+                    score / self.penalty as f32
+                } else {
+                    score * self.penalty as f32
+                };
+            }
+        }
+
+        Tensor::from_floats(data.as_slice(), &device).reshape([batch, vocab_size])
+    }
+}
+
+/// Frequency/presence penalty, as used by the OpenAI-style sampling APIs: subtracts
+/// `count * frequency + presence` from the logit of every token seen among the last `last_n`
+/// generated tokens, where `count` is how many times it occurred.
+pub struct FrequencyPresencePenalty {
+    pub frequency: f64,
+    pub presence: f64,
+    pub last_n: usize,
+}
+
+impl FrequencyPresencePenalty {
+    pub fn new(frequency: f64, presence: f64, last_n: usize) -> Self {
+        Self {
+            frequency,
+            presence,
+            last_n,
+        }
+    }
+
+    pub fn apply<B: Backend>(&self, logits: Tensor<B, 2>, generated: &[u32]) -> Tensor<B, 2> {
+        if self.frequency == 0.0 && self.presence == 0.0 {
+            return logits;
+        }
+
+        let device = logits.device();
+        let [batch, vocab_size] = logits.dims();
+        let mut data: Vec<f32> = logits.into_data().convert::<f32>().to_vec().unwrap();
+
+        let start = generated.len().saturating_sub(self.last_n);
+        let mut counts = HashMap::new();
+        for &token in &generated[start..] {
+            *counts.entry(token).or_insert(0u32) += 1;
+        }
+
+        for (token, count) in counts {
+            let idx = token as usize;
+            if idx < vocab_size {
+                data[idx] -= count as f32 * self.frequency as f32 + self.presence as f32;
+            }
+        }
+
+        Tensor::from_floats(data.as_slice(), &device).reshape([batch, vocab_size])
+    }
+}
+
+/// Nucleus (top-p) sampling: restricts the candidate set to the smallest set of highest
+/// probability tokens whose cumulative probability exceeds `p`, then samples from it.
+pub struct TopP {
+    p: f64,
+    rng: StdRng,
+}
+
+impl TopP {
+    pub fn new(p: f64, seed: u64) -> Self {
+        Self {
+            p,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Sampling for TopP {
+    fn sample<B: Backend>(&mut self, probs: Tensor<B, 2>) -> Tensor<B, 2, Int> {
+        let device = probs.device();
+        let (probs_sort, probs_idx) = probs.sort_descending_with_indices(1);
+
+        let probs_sort_cumsum = probs_sort.clone().cumsum(1);
+        let mask = probs_sort_cumsum.sub(probs_sort.clone()).greater_elem(self.p);
+        let probs_sort = probs_sort.mask_fill(mask, 0.0);
+
+        let next_token_idx = sample_multinomial(probs_sort, &mut self.rng, &device);
+        probs_idx.gather(1, next_token_idx)
+    }
+}
+
+fn sample_multinomial<B: Backend>(
+    probs: Tensor<B, 2>,
+    rng: &mut StdRng,
+    device: &B::Device,
+) -> Tensor<B, 2, Int> {
+    use rand::Rng;
+
+    let sum = probs.clone().sum_dim(1);
+    let probs = probs.div(sum);
+    let r: f64 = rng.gen();
+    let cumsum = probs.cumsum(1);
+    let mask = cumsum.greater_equal_elem(r);
+
+    mask.int().argmax(1).reshape([1, 1]).to_device(device)
+}
@@ -0,0 +1,291 @@
+use std::collections::HashSet;
+
+/// Common interface over the tokenizer implementations used by the supported Llama variants
+/// (SentencePiece for the original/TinyLlama models, Tiktoken-style BPE for Llama 3).
+pub trait Tokenizer {
+    fn encode(&self, text: &str, bos: bool, eos: bool) -> Vec<u32>;
+    fn decode(&self, tokens: Vec<u32>) -> String;
+    fn bos_id(&self) -> u32;
+    fn eos_id(&self) -> u32;
+    /// Additional token ids that should stop generation (e.g. `<|eot_id|>`), besides [`Tokenizer::eos_id`].
+    fn stop_ids(&self) -> HashSet<u32>;
+}
+
+/// Incrementally decodes a stream of generated token ids into UTF-8 text, one fragment at a
+/// time, without re-emitting text that was already returned.
+///
+/// Byte-level tokenizers can split a single codepoint across multiple token ids, so decoding a
+/// token in isolation can yield an incomplete (replacement-character-terminated) string. To
+/// handle this, we keep the full history of generated tokens plus a `prev_index`/`read_index`
+/// pair: `tokens[prev_index..read_index]` is the text already emitted, and on each new token we
+/// redecode `tokens[prev_index..]`. If that's longer than what's already been emitted and
+/// doesn't end in a dangling multi-byte sequence, the new suffix is safe to flush.
+pub struct TokenOutputStream<'a, T: Tokenizer> {
+    tokenizer: &'a T,
+    tokens: Vec<u32>,
+    prev_index: usize,
+    read_index: usize,
+}
+
+impl<'a, T: Tokenizer> TokenOutputStream<'a, T> {
+    pub fn new(tokenizer: &'a T) -> Self {
+        Self {
+            tokenizer,
+            tokens: Vec::new(),
+            prev_index: 0,
+            read_index: 0,
+        }
+    }
+
+    /// Record a newly generated token, returning the text fragment it completes, if any.
+    pub fn next_token(&mut self, token: u32) -> Option<String> {
+        self.tokens.push(token);
+
+        let prev_text = self.tokenizer.decode(self.tokens[self.prev_index..self.read_index].to_vec());
+        let text = self.tokenizer.decode(self.tokens[self.prev_index..].to_vec());
+
+        if text.len() > prev_text.len() && !text.ends_with('\u{fffd}') {
+            self.prev_index = self.read_index;
+            self.read_index = self.tokens.len();
+            Some(text[prev_text.len()..].to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Flush any remaining buffered text once generation has finished.
+    pub fn flush(&mut self) -> Option<String> {
+        let prev_text = self.tokenizer.decode(self.tokens[self.prev_index..self.read_index].to_vec());
+        let text = self.tokenizer.decode(self.tokens[self.prev_index..].to_vec());
+
+        if text.len() > prev_text.len() {
+            self.read_index = self.tokens.len();
+            Some(text[prev_text.len()..].to_string())
+        } else {
+            None
+        }
+    }
+
+    /// All token ids seen so far.
+    pub fn tokens(&self) -> &[u32] {
+        &self.tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A byte-level mock tokenizer: each token id maps to a fixed byte fragment, and `decode`
+    /// concatenates the fragments for the given ids and lossily re-decodes the result as UTF-8 —
+    /// mirroring how a real byte-level BPE tokenizer can produce a dangling replacement character
+    /// when a multi-byte codepoint is split across token ids that haven't all arrived yet.
+    struct ByteTokenizer {
+        table: Vec<Vec<u8>>,
+    }
+
+    impl Tokenizer for ByteTokenizer {
+        fn encode(&self, _text: &str, _bos: bool, _eos: bool) -> Vec<u32> {
+            Vec::new()
+        }
+
+        fn decode(&self, tokens: Vec<u32>) -> String {
+            let mut bytes = Vec::new();
+            for token in tokens {
+                bytes.extend_from_slice(&self.table[token as usize]);
+            }
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+
+        fn bos_id(&self) -> u32 {
+            0
+        }
+
+        fn eos_id(&self) -> u32 {
+            0
+        }
+
+        fn stop_ids(&self) -> HashSet<u32> {
+            HashSet::new()
+        }
+    }
+
+    #[test]
+    fn next_token_holds_back_a_split_multibyte_char() {
+        // "é" is 0xC3 0xA9 in UTF-8; split across two tokens, the first can't be decoded alone.
+        let tokenizer = ByteTokenizer {
+            table: vec![vec![0xC3], vec![0xA9]],
+        };
+        let mut stream = TokenOutputStream::new(&tokenizer);
+
+        assert_eq!(stream.next_token(0), None);
+        assert_eq!(stream.next_token(1), Some("é".to_string()));
+    }
+
+    #[test]
+    fn next_token_emits_complete_fragments_immediately() {
+        let tokenizer = ByteTokenizer {
+            table: vec![b"hello ".to_vec(), b"world".to_vec()],
+        };
+        let mut stream = TokenOutputStream::new(&tokenizer);
+
+        assert_eq!(stream.next_token(0), Some("hello ".to_string()));
+        assert_eq!(stream.next_token(1), Some("world".to_string()));
+        assert_eq!(stream.tokens(), &[0, 1]);
+    }
+
+    #[test]
+    fn flush_returns_none_when_nothing_is_held_back() {
+        let tokenizer = ByteTokenizer {
+            table: vec![b"hi".to_vec()],
+        };
+        let mut stream = TokenOutputStream::new(&tokenizer);
+
+        stream.next_token(0);
+        assert_eq!(stream.flush(), None);
+    }
+
+    #[test]
+    fn flush_emits_held_back_text_once_generation_finishes() {
+        let tokenizer = ByteTokenizer {
+            table: vec![vec![0xC3], vec![0xA9]],
+        };
+        let mut stream = TokenOutputStream::new(&tokenizer);
+
+        assert_eq!(stream.next_token(0), None); // held back: dangling replacement char
+        assert_eq!(stream.flush(), Some("\u{fffd}".to_string()));
+    }
+}
+
+#[cfg(feature = "pretrained")]
+mod sentencepiece {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// SentencePiece tokenizer, used by the original Llama and TinyLlama checkpoints.
+    pub struct SentencePieceTokenizer {
+        spp: tokenizers::Tokenizer,
+        bos_token_id: u32,
+        eos_token_id: u32,
+    }
+
+    impl SentencePieceTokenizer {
+        pub fn new(tokenizer_path: &str) -> Result<Self, String> {
+            let spp = tokenizers::Tokenizer::from_file(tokenizer_path).map_err(|e| e.to_string())?;
+
+            Ok(Self {
+                spp,
+                bos_token_id: 1,
+                eos_token_id: 2,
+            })
+        }
+    }
+
+    impl Tokenizer for SentencePieceTokenizer {
+        fn encode(&self, text: &str, bos: bool, eos: bool) -> Vec<u32> {
+            let mut tokens = self
+                .spp
+                .encode(text, false)
+                .map(|enc| enc.get_ids().to_vec())
+                .unwrap_or_default();
+
+            if bos {
+                tokens.insert(0, self.bos_token_id);
+            }
+            if eos {
+                tokens.push(self.eos_token_id);
+            }
+
+            tokens
+        }
+
+        fn decode(&self, tokens: Vec<u32>) -> String {
+            self.spp.decode(&tokens, true).unwrap_or_default()
+        }
+
+        fn bos_id(&self) -> u32 {
+            self.bos_token_id
+        }
+
+        fn eos_id(&self) -> u32 {
+            self.eos_token_id
+        }
+
+        fn stop_ids(&self) -> HashSet<u32> {
+            HashSet::from([self.eos_token_id])
+        }
+    }
+}
+
+#[cfg(feature = "pretrained")]
+pub use sentencepiece::SentencePieceTokenizer;
+
+#[cfg(feature = "pretrained")]
+mod tiktoken {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// BPE tokenizer used by Llama 3, with the chat-turn special tokens it relies on.
+    pub struct Tiktoken {
+        bpe: tokenizers::Tokenizer,
+        bos_token_id: u32,
+        eos_token_id: u32,
+        eot_token_id: u32,
+    }
+
+    impl Tiktoken {
+        pub fn new(tokenizer_path: &str) -> Result<Self, String> {
+            let bpe = tokenizers::Tokenizer::from_file(tokenizer_path).map_err(|e| e.to_string())?;
+
+            let token_id = |token: &str| {
+                bpe.token_to_id(token)
+                    .unwrap_or_else(|| panic!("Should find special token '{token}'"))
+            };
+
+            Ok(Self {
+                bos_token_id: token_id("<|begin_of_text|>"),
+                eos_token_id: token_id("<|end_of_text|>"),
+                eot_token_id: token_id("<|eot_id|>"),
+                bpe,
+            })
+        }
+    }
+
+    impl Tokenizer for Tiktoken {
+        fn encode(&self, text: &str, bos: bool, eos: bool) -> Vec<u32> {
+            let mut tokens = self
+                .bpe
+                .encode(text, false)
+                .map(|enc| enc.get_ids().to_vec())
+                .unwrap_or_default();
+
+            if bos {
+                tokens.insert(0, self.bos_token_id);
+            }
+            if eos {
+                tokens.push(self.eos_token_id);
+            }
+
+            tokens
+        }
+
+        fn decode(&self, tokens: Vec<u32>) -> String {
+            self.bpe.decode(&tokens, true).unwrap_or_default()
+        }
+
+        fn bos_id(&self) -> u32 {
+            self.bos_token_id
+        }
+
+        fn eos_id(&self) -> u32 {
+            self.eos_token_id
+        }
+
+        fn stop_ids(&self) -> HashSet<u32> {
+            HashSet::from([self.eos_token_id, self.eot_token_id])
+        }
+    }
+}
+
+#[cfg(feature = "pretrained")]
+pub use tiktoken::Tiktoken;
@@ -0,0 +1,361 @@
+use burn::{
+    config::Config,
+    module::{Module, Param},
+    nn::{Embedding, EmbeddingConfig, Linear, LinearConfig, RmsNorm, RmsNormConfig},
+    tensor::{backend::Backend, Device, Tensor, TensorData},
+};
+
+use crate::{cache::AutoregressiveCache, gguf::GgufFile};
+
+/// Configuration for the Llama transformer body (embeddings, attention blocks, and output head).
+#[derive(Config, Debug)]
+pub struct TransformerLlamaConfig {
+    pub vocab_size: usize,
+    pub d_model: usize,
+    pub hidden_size: usize,
+    pub num_layers: usize,
+    pub num_heads: usize,
+    pub num_kv_heads: usize,
+    pub max_seq_len: usize,
+    #[config(default = "1e-5")]
+    pub norm_eps: f64,
+    /// Base for the rotary position embedding's inverse frequencies (`theta` in the RoPE paper).
+    /// Llama-1/2 use the default 10000.0; Llama-3 uses 500000.0 to extend further in context
+    /// length before the rotation period repeats.
+    #[config(default = "10000.0")]
+    pub rope_theta: f64,
+}
+
+impl TransformerLlamaConfig {
+    pub fn init<B: Backend>(&self, device: &Device<B>) -> TransformerLlama<B> {
+        let layers = (0..self.num_layers)
+            .map(|_| TransformerBlock::new(self, device))
+            .collect();
+
+        TransformerLlama {
+            tok_embeddings: EmbeddingConfig::new(self.vocab_size, self.d_model).init(device),
+            layers,
+            norm: RmsNormConfig::new(self.d_model).with_epsilon(self.norm_eps).init(device),
+            output: LinearConfig::new(self.d_model, self.vocab_size)
+                .with_bias(false)
+                .init(device),
+        }
+    }
+}
+
+/// A single decoder block: self-attention (with rotary position embeddings and a KV cache) and
+/// a SwiGLU feed-forward network, each behind a pre-norm residual connection.
+#[derive(Module, Debug)]
+pub struct TransformerBlock<B: Backend> {
+    attention_norm: RmsNorm<B>,
+    attention: Attention<B>,
+    ffn_norm: RmsNorm<B>,
+    feed_forward: FeedForward<B>,
+}
+
+impl<B: Backend> TransformerBlock<B> {
+    fn new(config: &TransformerLlamaConfig, device: &Device<B>) -> Self {
+        Self {
+            attention_norm: RmsNormConfig::new(config.d_model)
+                .with_epsilon(config.norm_eps)
+                .init(device),
+            attention: Attention::new(config, device),
+            ffn_norm: RmsNormConfig::new(config.d_model)
+                .with_epsilon(config.norm_eps)
+                .init(device),
+            feed_forward: FeedForward::new(config, device),
+        }
+    }
+
+    fn forward(&self, x: Tensor<B, 3>, cache: &mut AutoregressiveCache<B>) -> Tensor<B, 3> {
+        let h = x.clone() + self.attention.forward(self.attention_norm.forward(x), cache);
+        h.clone() + self.feed_forward.forward(self.ffn_norm.forward(h))
+    }
+}
+
+#[derive(Module, Debug)]
+pub struct Attention<B: Backend> {
+    wq: Linear<B>,
+    wk: Linear<B>,
+    wv: Linear<B>,
+    wo: Linear<B>,
+    num_heads: usize,
+    num_kv_heads: usize,
+    head_dim: usize,
+    rope_theta: f64,
+}
+
+impl<B: Backend> Attention<B> {
+    fn new(config: &TransformerLlamaConfig, device: &Device<B>) -> Self {
+        let head_dim = config.d_model / config.num_heads;
+        let kv_dim = head_dim * config.num_kv_heads;
+
+        Self {
+            wq: LinearConfig::new(config.d_model, config.d_model)
+                .with_bias(false)
+                .init(device),
+            wk: LinearConfig::new(config.d_model, kv_dim).with_bias(false).init(device),
+            wv: LinearConfig::new(config.d_model, kv_dim).with_bias(false).init(device),
+            wo: LinearConfig::new(config.d_model, config.d_model)
+                .with_bias(false)
+                .init(device),
+            num_heads: config.num_heads,
+            num_kv_heads: config.num_kv_heads,
+            head_dim,
+            rope_theta: config.rope_theta,
+        }
+    }
+
+    fn forward(&self, x: Tensor<B, 3>, cache: &mut AutoregressiveCache<B>) -> Tensor<B, 3> {
+        // Projects `x` to per-head queries/keys/values, applies rotary position embeddings,
+        // appends the rotated keys/values to `cache`, expands the kv heads to match the query
+        // heads (grouped-query attention, when `num_kv_heads < num_heads`), and runs causal
+        // scaled dot-product attention before the output projection.
+        let [batch, seq_len, _] = x.dims();
+        let device = x.device();
+        let position_offset = cache.len();
+
+        let q = self
+            .wq
+            .forward(x.clone())
+            .reshape([batch, seq_len, self.num_heads, self.head_dim])
+            .swap_dims(1, 2);
+        let k = self
+            .wk
+            .forward(x.clone())
+            .reshape([batch, seq_len, self.num_kv_heads, self.head_dim])
+            .swap_dims(1, 2);
+        let v = self
+            .wv
+            .forward(x)
+            .reshape([batch, seq_len, self.num_kv_heads, self.head_dim])
+            .swap_dims(1, 2);
+
+        let q = rotary_embedding(q, position_offset, self.rope_theta);
+        let k = rotary_embedding(k, position_offset, self.rope_theta);
+
+        let (k, v) = cache.forward(k, v);
+        let kv_len = k.dims()[2];
+
+        let n_rep = self.num_heads / self.num_kv_heads;
+        let k = repeat_kv_heads(k, n_rep);
+        let v = repeat_kv_heads(v, n_rep);
+
+        let scores = q.matmul(k.swap_dims(2, 3)) / (self.head_dim as f64).sqrt();
+        let scores = scores + causal_mask::<B>(seq_len, kv_len, position_offset, &device);
+        let probs = burn::tensor::activation::softmax(scores, 3);
+
+        let out = probs.matmul(v).swap_dims(1, 2).reshape([batch, seq_len, self.num_heads * self.head_dim]);
+        self.wo.forward(out)
+    }
+}
+
+/// Applies rotary position embeddings to `x` (shaped `[batch, heads, seq_len, head_dim]`), whose
+/// first token sits at absolute position `position_offset` (the number of tokens already in the
+/// KV cache before this step).
+fn rotary_embedding<B: Backend>(x: Tensor<B, 4>, position_offset: usize, theta: f64) -> Tensor<B, 4> {
+    let [_, _, seq_len, head_dim] = x.dims();
+    let device = x.device();
+    let half = head_dim / 2;
+
+    let mut angles = Vec::with_capacity(seq_len * half);
+    for i in 0..seq_len {
+        let position = (i + position_offset) as f64;
+        for j in 0..half {
+            let inv_freq = 1.0 / theta.powf(2.0 * j as f64 / head_dim as f64);
+            angles.push((position * inv_freq) as f32);
+        }
+    }
+    let angles = Tensor::<B, 1>::from_floats(angles.as_slice(), &device).reshape([seq_len, half]);
+    let cos = Tensor::cat(vec![angles.clone().cos(), angles.clone().cos()], 1).reshape([1, 1, seq_len, head_dim]);
+    let sin = Tensor::cat(vec![angles.clone().sin(), angles.sin()], 1).reshape([1, 1, seq_len, head_dim]);
+
+    let x1 = x.clone().narrow(3, 0, half);
+    let x2 = x.clone().narrow(3, half, head_dim - half);
+    let rotated = Tensor::cat(vec![-x2, x1], 3);
+
+    x * cos + rotated * sin
+}
+
+/// Repeats each of `x`'s kv heads (shaped `[batch, num_kv_heads, seq_len, head_dim]`) `n_rep`
+/// times so it lines up with the (larger) number of query heads in grouped-query attention.
+fn repeat_kv_heads<B: Backend>(x: Tensor<B, 4>, n_rep: usize) -> Tensor<B, 4> {
+    if n_rep == 1 {
+        return x;
+    }
+
+    let [batch, num_kv_heads, seq_len, head_dim] = x.dims();
+    x.unsqueeze_dim::<5>(2)
+        .expand([batch, num_kv_heads, n_rep, seq_len, head_dim])
+        .reshape([batch, num_kv_heads * n_rep, seq_len, head_dim])
+}
+
+/// Additive causal mask of shape `[1, 1, seq_len, kv_len]`: `0.0` where the query at
+/// `position_offset + i` may attend to the key at position `j`, `-inf` where `j` is in the
+/// future relative to that query (masked out before the softmax).
+fn causal_mask<B: Backend>(seq_len: usize, kv_len: usize, position_offset: usize, device: &Device<B>) -> Tensor<B, 4> {
+    let mut data = vec![0f32; seq_len * kv_len];
+    for i in 0..seq_len {
+        for j in (position_offset + i + 1)..kv_len {
+            data[i * kv_len + j] = f32::NEG_INFINITY;
+        }
+    }
+    Tensor::<B, 1>::from_floats(data.as_slice(), device).reshape([1, 1, seq_len, kv_len])
+}
+
+#[derive(Module, Debug)]
+pub struct FeedForward<B: Backend> {
+    w1: Linear<B>,
+    w2: Linear<B>,
+    w3: Linear<B>,
+}
+
+impl<B: Backend> FeedForward<B> {
+    fn new(config: &TransformerLlamaConfig, device: &Device<B>) -> Self {
+        Self {
+            w1: LinearConfig::new(config.d_model, config.hidden_size)
+                .with_bias(false)
+                .init(device),
+            w2: LinearConfig::new(config.hidden_size, config.d_model)
+                .with_bias(false)
+                .init(device),
+            w3: LinearConfig::new(config.d_model, config.hidden_size)
+                .with_bias(false)
+                .init(device),
+        }
+    }
+
+    fn forward(&self, x: Tensor<B, 3>) -> Tensor<B, 3> {
+        let swish = burn::tensor::activation::silu(self.w1.forward(x.clone()));
+        self.w2.forward(swish * self.w3.forward(x))
+    }
+}
+
+/// The Llama transformer body: token embeddings, a stack of decoder blocks, and the final norm
+/// and LM head.
+#[derive(Module, Debug)]
+pub struct TransformerLlama<B: Backend> {
+    tok_embeddings: Embedding<B>,
+    layers: Vec<TransformerBlock<B>>,
+    norm: RmsNorm<B>,
+    output: Linear<B>,
+}
+
+impl<B: Backend> TransformerLlama<B> {
+    /// Runs the embeddings and decoder stack, returning the final hidden states (pre-LM-head).
+    pub fn forward_hidden(
+        &self,
+        tokens: Tensor<B, 2, burn::tensor::Int>,
+        cache: &mut Vec<AutoregressiveCache<B>>,
+    ) -> Tensor<B, 3> {
+        let mut x = self.tok_embeddings.forward(tokens);
+
+        for (layer, cache) in self.layers.iter().zip(cache.iter_mut()) {
+            x = layer.forward(x, cache);
+        }
+
+        self.norm.forward(x)
+    }
+
+    /// Runs the full forward pass and projects the hidden states to vocabulary logits.
+    pub fn forward(
+        &self,
+        tokens: Tensor<B, 2, burn::tensor::Int>,
+        cache: &mut Vec<AutoregressiveCache<B>>,
+    ) -> Tensor<B, 3> {
+        self.output.forward(self.forward_hidden(tokens, cache))
+    }
+
+    /// Construct the model directly from a parsed GGUF checkpoint, dequantizing each tensor on
+    /// the fly and assigning it to the matching module using the llama.cpp tensor naming
+    /// convention (`blk.{i}.attn_q.weight`, `output_norm.weight`, ...).
+    pub fn from_gguf(
+        gguf: &GgufFile,
+        config: &TransformerLlamaConfig,
+        device: &Device<B>,
+    ) -> Result<Self, crate::gguf::GgufError> {
+        let mut layers = Vec::with_capacity(config.num_layers);
+        for i in 0..config.num_layers {
+            layers.push(TransformerBlock {
+                attention_norm: rms_norm(gguf, &format!("blk.{i}.attn_norm.weight"), config.norm_eps, device)?,
+                attention: Attention {
+                    wq: linear(gguf, &format!("blk.{i}.attn_q.weight"), device)?,
+                    wk: linear(gguf, &format!("blk.{i}.attn_k.weight"), device)?,
+                    wv: linear(gguf, &format!("blk.{i}.attn_v.weight"), device)?,
+                    wo: linear(gguf, &format!("blk.{i}.attn_output.weight"), device)?,
+                    num_heads: config.num_heads,
+                    num_kv_heads: config.num_kv_heads,
+                    head_dim: config.d_model / config.num_heads,
+                    rope_theta: config.rope_theta,
+                },
+                ffn_norm: rms_norm(gguf, &format!("blk.{i}.ffn_norm.weight"), config.norm_eps, device)?,
+                feed_forward: FeedForward {
+                    w1: linear(gguf, &format!("blk.{i}.ffn_gate.weight"), device)?,
+                    w2: linear(gguf, &format!("blk.{i}.ffn_down.weight"), device)?,
+                    w3: linear(gguf, &format!("blk.{i}.ffn_up.weight"), device)?,
+                },
+            });
+        }
+
+        Ok(Self {
+            tok_embeddings: embedding(gguf, "token_embd.weight", device)?,
+            layers,
+            norm: rms_norm(gguf, "output_norm.weight", config.norm_eps, device)?,
+            output: linear(gguf, "output.weight", device)?,
+        })
+    }
+}
+
+fn tensor2<B: Backend>(
+    gguf: &GgufFile,
+    name: &str,
+    device: &Device<B>,
+) -> Result<Tensor<B, 2>, crate::gguf::GgufError> {
+    let (data, shape) = gguf.load_tensor(name)?;
+    Ok(Tensor::from_data(TensorData::new(data, shape), device))
+}
+
+fn tensor1<B: Backend>(
+    gguf: &GgufFile,
+    name: &str,
+    device: &Device<B>,
+) -> Result<Tensor<B, 1>, crate::gguf::GgufError> {
+    let (data, shape) = gguf.load_tensor(name)?;
+    Ok(Tensor::from_data(TensorData::new(data, shape), device))
+}
+
+fn linear<B: Backend>(
+    gguf: &GgufFile,
+    name: &str,
+    device: &Device<B>,
+) -> Result<Linear<B>, crate::gguf::GgufError> {
+    // GGUF stores the projection weight as [out, in]; burn's `Linear` expects [in, out], so it's
+    // transposed once on load rather than on every forward pass.
+    let weight = tensor2(gguf, name, device)?.transpose();
+    Ok(Linear {
+        weight: Param::from_tensor(weight),
+        bias: None,
+    })
+}
+
+fn embedding<B: Backend>(
+    gguf: &GgufFile,
+    name: &str,
+    device: &Device<B>,
+) -> Result<Embedding<B>, crate::gguf::GgufError> {
+    Ok(Embedding {
+        weight: Param::from_tensor(tensor2(gguf, name, device)?),
+    })
+}
+
+fn rms_norm<B: Backend>(
+    gguf: &GgufFile,
+    name: &str,
+    epsilon: f64,
+    device: &Device<B>,
+) -> Result<RmsNorm<B>, crate::gguf::GgufError> {
+    Ok(RmsNorm {
+        gamma: Param::from_tensor(tensor1(gguf, name, device)?),
+        epsilon,
+    })
+}